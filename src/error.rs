@@ -0,0 +1,166 @@
+// 统一的应用错误类型，所有处理函数都通过 `?` 将错误转换为 `AppError`
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use validator::ValidationErrors;
+
+// 应用级错误，区分数据库错误、资源未找到、请求参数错误、字段校验错误和其他内部错误
+#[derive(Debug)]
+pub enum AppError {
+    Db(tokio_postgres::Error),
+    Pool(bb8::RunError<tokio_postgres::Error>),
+    NotFound(String),
+    BadRequest(String),
+    Validation(ValidationErrors),
+    Internal(String),
+}
+
+impl AppError {
+    // 每种错误对应的业务码，约定 0 表示成功
+    fn code(&self) -> i32 {
+        match self {
+            AppError::NotFound(_) => 2,
+            AppError::BadRequest(_) | AppError::Validation(_) => 3,
+            AppError::Db(_) | AppError::Pool(_) => 4,
+            AppError::Internal(_) => 5,
+        }
+    }
+
+    fn status(&self) -> StatusCode {
+        match self {
+            AppError::NotFound(_) => StatusCode::NOT_FOUND,
+            AppError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            AppError::Validation(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            AppError::Db(_) | AppError::Pool(_) | AppError::Internal(_) => {
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        }
+    }
+
+    fn message(&self) -> String {
+        match self {
+            AppError::NotFound(msg) => msg.clone(),
+            AppError::BadRequest(msg) => msg.clone(),
+            AppError::Validation(_) => "validation failed".to_string(),
+            // 数据库/连接池的原始错误可能带表名、字段名甚至约束名，只记服务端日志，
+            // 不回显给客户端
+            AppError::Db(_) | AppError::Pool(_) => "internal error".to_string(),
+            AppError::Internal(msg) => msg.clone(),
+        }
+    }
+
+    // 把只在服务端可见的详细错误记下来，客户端响应里只留通用的msg
+    fn log(&self) {
+        match self {
+            AppError::Db(err) => tracing::error!("database error: {}", err),
+            AppError::Pool(err) => tracing::error!("connection pool error: {}", err),
+            _ => {}
+        }
+    }
+}
+
+impl From<tokio_postgres::Error> for AppError {
+    fn from(err: tokio_postgres::Error) -> Self {
+        AppError::Db(err)
+    }
+}
+
+impl From<bb8::RunError<tokio_postgres::Error>> for AppError {
+    fn from(err: bb8::RunError<tokio_postgres::Error>) -> Self {
+        AppError::Pool(err)
+    }
+}
+
+impl From<ValidationErrors> for AppError {
+    fn from(err: ValidationErrors) -> Self {
+        AppError::Validation(err)
+    }
+}
+
+// 把`validator`的字段错误整理成`{ field: [reason, ...] }`，作为422响应的data
+fn validation_fields(errors: &ValidationErrors) -> Map<String, Value> {
+    errors
+        .field_errors()
+        .into_iter()
+        .map(|(field, errs)| {
+            let reasons: Vec<String> = errs
+                .iter()
+                .map(|e| {
+                    e.message
+                        .clone()
+                        .map(|m| m.to_string())
+                        .unwrap_or_else(|| e.code.to_string())
+                })
+                .collect();
+            (field.to_string(), Value::from(reasons))
+        })
+        .collect()
+}
+
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
+        self.log();
+        let status = self.status();
+        let code = self.code();
+
+        if let AppError::Validation(errors) = &self {
+            let body = Envelope {
+                code,
+                msg: self.message(),
+                data: Some(validation_fields(errors)),
+            };
+            return (status, Json(body)).into_response();
+        }
+
+        let body = Envelope::<()> {
+            code,
+            msg: self.message(),
+            data: None,
+        };
+        (status, Json(body)).into_response()
+    }
+}
+
+// 统一的响应信封，成功和失败都复用同一个结构：{ code, msg, data }
+#[derive(Debug, Serialize)]
+pub struct Envelope<T> {
+    pub code: i32,
+    pub msg: String,
+    pub data: Option<T>,
+}
+
+// 成功响应的包装类型，配合 `IntoResponse` 自动套上信封并附带状态码
+pub struct Resp<T> {
+    pub status: StatusCode,
+    pub data: T,
+}
+
+impl<T> Resp<T> {
+    pub fn new(status: StatusCode, data: T) -> Self {
+        Self { status, data }
+    }
+
+    pub fn ok(data: T) -> Self {
+        Self::new(StatusCode::OK, data)
+    }
+}
+
+impl<T: Serialize> IntoResponse for Resp<T> {
+    fn into_response(self) -> Response {
+        // RFC 9110 §15.3.5 禁止204响应带body，204在这里永远不套信封
+        if self.status == StatusCode::NO_CONTENT {
+            return self.status.into_response();
+        }
+
+        let body = Envelope {
+            code: 0,
+            msg: "OK".to_string(),
+            data: Some(self.data),
+        };
+        (self.status, Json(body)).into_response()
+    }
+}
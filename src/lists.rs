@@ -0,0 +1,253 @@
+// todo_list -> todo_item 两级子系统的数据结构、处理函数和路由
+use axum::{
+    extract::{Json, Path, Query, State},
+    http::StatusCode,
+    routing::{get, post, put},
+    Router,
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::error::{AppError, Resp};
+use crate::{ConnectionPool, Pagination};
+
+// 定义清单的数据结构
+#[derive(Debug, Serialize, Clone)]
+pub struct TodoList {
+    pub id: String,
+    pub title: String,
+}
+
+// 定义创建清单的数据结构
+#[derive(Debug, Deserialize)]
+pub struct CreateTodoList {
+    pub title: String,
+}
+
+// 定义更新清单的数据结构
+#[derive(Debug, Deserialize)]
+pub struct UpdateTodoList {
+    pub title: Option<String>,
+}
+
+// 定义清单条目的数据结构
+#[derive(Debug, Serialize, Clone)]
+pub struct TodoItem {
+    pub id: String,
+    pub list_id: String,
+    pub title: String,
+    pub checked: bool,
+}
+
+// 定义创建清单条目的数据结构
+#[derive(Debug, Deserialize)]
+pub struct CreateTodoItem {
+    pub title: String,
+}
+
+// 定义更新清单条目的数据结构
+#[derive(Debug, Deserialize)]
+pub struct UpdateTodoItem {
+    pub title: Option<String>,
+    pub checked: Option<bool>,
+}
+
+// 组装todo_list/todo_item子系统的所有路由
+pub fn routes() -> Router<ConnectionPool> {
+    Router::new()
+        .route("/lists", get(lists_list).post(list_create)) // 定义GET/POST /lists路由
+        .route(
+            "/lists/:id",
+            get(list_get).put(list_update).delete(list_delete),
+        ) // 定义GET/PUT/DELETE /lists/:id路由
+        .route(
+            "/lists/:id/items",
+            get(items_list).post(item_create),
+        ) // 定义GET/POST /lists/:id/items路由
+        .route("/items/:id", put(item_update)) // 定义PUT /items/:id路由
+}
+
+// 列出所有清单的处理函数
+async fn lists_list(
+    pagination: Option<Query<Pagination>>, // 复用现有的分页查询提取器
+    State(pool): State<ConnectionPool>,
+) -> Result<Resp<Vec<TodoList>>, AppError> {
+    let conn = pool.get().await?;
+    let Query(pagination) = pagination.unwrap_or_default();
+    let offset: i64 = pagination.offset.unwrap_or(0);
+    let limit: i64 = pagination.limit.unwrap_or(100);
+
+    let rows = conn
+        .query(
+            "select id, title from todo_list offset $1 limit $2",
+            &[&offset, &limit],
+        )
+        .await?;
+
+    let lists = rows
+        .into_iter()
+        .map(|row| TodoList {
+            id: row.get(0),
+            title: row.get(1),
+        })
+        .collect();
+
+    Ok(Resp::ok(lists))
+}
+
+// 创建清单的处理函数
+async fn list_create(
+    State(pool): State<ConnectionPool>,
+    Json(input): Json<CreateTodoList>,
+) -> Result<Resp<TodoList>, AppError> {
+    let list = TodoList {
+        id: Uuid::new_v4().simple().to_string(),
+        title: input.title,
+    };
+
+    let conn = pool.get().await?;
+    conn.execute(
+        "insert into todo_list (id, title) values ($1, $2)",
+        &[&list.id, &list.title],
+    )
+    .await?;
+
+    Ok(Resp::new(StatusCode::CREATED, list))
+}
+
+// 获取单个清单的处理函数
+async fn list_get(
+    Path(id): Path<String>,
+    State(pool): State<ConnectionPool>,
+) -> Result<Resp<TodoList>, AppError> {
+    let conn = pool.get().await?;
+    let row = conn
+        .query_opt("select id, title from todo_list where id = $1", &[&id])
+        .await?
+        .ok_or_else(|| AppError::NotFound("record not found".to_string()))?;
+
+    Ok(Resp::ok(TodoList {
+        id: row.get(0),
+        title: row.get(1),
+    }))
+}
+
+// 更新清单的处理函数
+async fn list_update(
+    Path(id): Path<String>,
+    State(pool): State<ConnectionPool>,
+    Json(input): Json<UpdateTodoList>,
+) -> Result<Resp<TodoList>, AppError> {
+    let conn = pool.get().await?;
+    let row = conn
+        .query_opt(
+            "update todo_list set title = coalesce($2, title) where id = $1 returning id, title",
+            &[&id, &input.title],
+        )
+        .await?
+        .ok_or_else(|| AppError::NotFound("record not found".to_string()))?;
+
+    Ok(Resp::ok(TodoList {
+        id: row.get(0),
+        title: row.get(1),
+    }))
+}
+
+// 删除清单的处理函数
+async fn list_delete(
+    Path(id): Path<String>,
+    State(pool): State<ConnectionPool>,
+) -> Result<Resp<()>, AppError> {
+    let conn = pool.get().await?;
+    let deleted = conn
+        .execute("delete from todo_list where id = $1", &[&id])
+        .await?;
+
+    if deleted == 0 {
+        return Err(AppError::NotFound("record not found".to_string()));
+    }
+
+    Ok(Resp::new(StatusCode::NO_CONTENT, ()))
+}
+
+// 列出某个清单下所有条目的处理函数
+async fn items_list(
+    Path(list_id): Path<String>,
+    State(pool): State<ConnectionPool>,
+) -> Result<Resp<Vec<TodoItem>>, AppError> {
+    let conn = pool.get().await?;
+    let rows = conn
+        .query(
+            "select id, list_id, title, checked from todo_item where list_id = $1",
+            &[&list_id],
+        )
+        .await?;
+
+    let items = rows
+        .into_iter()
+        .map(|row| TodoItem {
+            id: row.get(0),
+            list_id: row.get(1),
+            title: row.get(2),
+            checked: row.get(3),
+        })
+        .collect();
+
+    Ok(Resp::ok(items))
+}
+
+// 在某个清单下创建条目的处理函数，清单不存在时返回400
+async fn item_create(
+    Path(list_id): Path<String>,
+    State(pool): State<ConnectionPool>,
+    Json(input): Json<CreateTodoItem>,
+) -> Result<Resp<TodoItem>, AppError> {
+    let conn = pool.get().await?;
+
+    let list_exists = conn
+        .query_opt("select id from todo_list where id = $1", &[&list_id])
+        .await?
+        .is_some();
+
+    if !list_exists {
+        return Err(AppError::BadRequest("list_id does not exist".to_string()));
+    }
+
+    let item = TodoItem {
+        id: Uuid::new_v4().simple().to_string(),
+        list_id,
+        title: input.title,
+        checked: false,
+    };
+
+    conn.execute(
+        "insert into todo_item (id, list_id, title, checked) values ($1, $2, $3, $4)",
+        &[&item.id, &item.list_id, &item.title, &item.checked],
+    )
+    .await?;
+
+    Ok(Resp::new(StatusCode::CREATED, item))
+}
+
+// 更新条目的处理函数
+async fn item_update(
+    Path(id): Path<String>,
+    State(pool): State<ConnectionPool>,
+    Json(input): Json<UpdateTodoItem>,
+) -> Result<Resp<TodoItem>, AppError> {
+    let conn = pool.get().await?;
+    let row = conn
+        .query_opt(
+            "update todo_item set title = coalesce($2, title), checked = coalesce($3, checked) where id = $1 returning id, list_id, title, checked",
+            &[&id, &input.title, &input.checked],
+        )
+        .await?
+        .ok_or_else(|| AppError::NotFound("record not found".to_string()))?;
+
+    Ok(Resp::ok(TodoItem {
+        id: row.get(0),
+        list_id: row.get(1),
+        title: row.get(2),
+        checked: row.get(3),
+    }))
+}
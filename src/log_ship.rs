@@ -0,0 +1,274 @@
+// 可选的日志转发层，把结构化日志批量POST到远程日志收集器
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde::Serialize;
+use serde_json::{Map, Value};
+use tokio::sync::{mpsc, oneshot};
+use tokio::task::JoinHandle;
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record as SpanRecord};
+use tracing::{Event, Subscriber};
+use tracing_subscriber::layer::{Context, Layer};
+use tracing_subscriber::registry::LookupSpan;
+
+// 批量大小和flush周期，达到其一即触发一次发送
+const BATCH_SIZE: usize = 100;
+const FLUSH_INTERVAL: Duration = Duration::from_secs(5);
+// channel容量，写满后丢弃新日志而不是阻塞请求处理
+const CHANNEL_CAPACITY: usize = 1024;
+
+// 单条日志记录，序列化后即为一行NDJSON
+#[derive(Debug, Serialize)]
+struct LogRecord {
+    timestamp: String,
+    level: String,
+    target: String,
+    message: String,
+    #[serde(flatten)]
+    fields: Map<String, Value>,
+}
+
+// 把tracing字段收集成一个JSON对象，`message`字段单独抽出来
+#[derive(Default)]
+struct FieldVisitor {
+    message: String,
+    fields: Map<String, Value>,
+}
+
+// 挂在每个span extensions上的缓存，记录该span自身携带的字段
+struct SpanFields(Map<String, Value>);
+
+impl Visit for FieldVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message = value.to_string();
+        } else {
+            self.fields
+                .insert(field.name().to_string(), Value::String(value.to_string()));
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        let rendered = format!("{:?}", value);
+        if field.name() == "message" {
+            self.message = rendered;
+        } else {
+            self.fields.insert(field.name().to_string(), Value::String(rendered));
+        }
+    }
+}
+
+// 自定义`Layer`，把每个事件整理成`LogRecord`后推入有界channel，JSON序列化和发送都交给后台任务完成
+pub struct LogShipLayer {
+    sender: mpsc::Sender<LogRecord>,
+    dropped: Arc<AtomicU64>,
+}
+
+// 持有关闭后台发送任务所需的句柄，在进程优雅关闭时由调用方await
+pub struct ShipperHandle {
+    shutdown_tx: oneshot::Sender<()>,
+    join: JoinHandle<()>,
+}
+
+impl ShipperHandle {
+    // 通知后台任务把剩余记录flush完，再等它退出
+    pub async fn shutdown(self) {
+        let _ = self.shutdown_tx.send(());
+        let _ = self.join.await;
+    }
+}
+
+impl LogShipLayer {
+    // 创建日志转发层，同时启动负责批量发送的后台任务；返回的`ShipperHandle`
+    // 用于在进程退出前触发一次最终flush
+    pub fn new(
+        url: String,
+        username: Option<String>,
+        password: Option<String>,
+    ) -> (Self, ShipperHandle) {
+        let (tx, rx) = mpsc::channel(CHANNEL_CAPACITY);
+        let dropped = Arc::new(AtomicU64::new(0));
+        let (shutdown_tx, shutdown_rx) = oneshot::channel();
+
+        let join = tokio::spawn(run_shipper(
+            rx,
+            url,
+            username,
+            password,
+            dropped.clone(),
+            shutdown_rx,
+        ));
+
+        (
+            Self {
+                sender: tx,
+                dropped,
+            },
+            ShipperHandle { shutdown_tx, join },
+        )
+    }
+}
+
+impl<S> Layer<S> for LogShipLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    // 新建span时把它自身携带的字段缓存到extensions里，供事件发生时回填
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let mut visitor = FieldVisitor::default();
+        attrs.record(&mut visitor);
+
+        if let Some(span) = ctx.span(id) {
+            span.extensions_mut().insert(SpanFields(visitor.fields));
+        }
+    }
+
+    // span通过`record`追加字段时同步更新缓存
+    fn on_record(&self, id: &Id, values: &SpanRecord<'_>, ctx: Context<'_, S>) {
+        let mut visitor = FieldVisitor::default();
+        values.record(&mut visitor);
+
+        if let Some(span) = ctx.span(id) {
+            let mut extensions = span.extensions_mut();
+            if let Some(SpanFields(fields)) = extensions.get_mut::<SpanFields>() {
+                fields.extend(visitor.fields);
+            }
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let mut visitor = FieldVisitor::default();
+        event.record(&mut visitor);
+
+        let mut fields = visitor.fields;
+
+        // 把当前span及其所有父span携带的字段一并合入记录，事件本身的字段优先
+        if let Some(leaf) = ctx.lookup_current() {
+            for span in leaf.scope() {
+                if let Some(SpanFields(span_fields)) = span.extensions().get::<SpanFields>() {
+                    for (key, value) in span_fields {
+                        fields.entry(key.clone()).or_insert_with(|| value.clone());
+                    }
+                }
+            }
+        }
+
+        let record = LogRecord {
+            timestamp: now_rfc3339(),
+            level: event.metadata().level().to_string(),
+            target: event.metadata().target().to_string(),
+            message: visitor.message,
+            fields,
+        };
+
+        // 背压时直接丢弃并计数，日志转发不允许拖慢请求处理
+        if self.sender.try_send(record).is_err() {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+// 生成一个不依赖`chrono`的RFC3339日历时间戳，例如"2026-07-29T12:00:00.123456789Z"
+fn now_rfc3339() -> String {
+    time::OffsetDateTime::now_utc()
+        .format(&time::format_description::well_known::Rfc3339)
+        .unwrap_or_default()
+}
+
+// 后台任务：攒够一批或者到达flush周期就发送一次；收到关闭信号或channel彻底关闭后，
+// 把残留在channel里和batch里的记录都发完再退出
+async fn run_shipper(
+    mut rx: mpsc::Receiver<LogRecord>,
+    url: String,
+    username: Option<String>,
+    password: Option<String>,
+    dropped: Arc<AtomicU64>,
+    mut shutdown_rx: oneshot::Receiver<()>,
+) {
+    let client = reqwest::Client::new();
+    let mut batch: Vec<LogRecord> = Vec::with_capacity(BATCH_SIZE);
+    let mut interval = tokio::time::interval(FLUSH_INTERVAL);
+
+    loop {
+        tokio::select! {
+            record = rx.recv() => {
+                match record {
+                    Some(record) => {
+                        batch.push(record);
+                        if batch.len() >= BATCH_SIZE {
+                            flush(&client, &url, &username, &password, &mut batch).await;
+                        }
+                    }
+                    None => {
+                        // 发送端已全部释放，把剩余记录发完后退出
+                        flush(&client, &url, &username, &password, &mut batch).await;
+                        break;
+                    }
+                }
+            }
+            _ = interval.tick() => {
+                flush(&client, &url, &username, &password, &mut batch).await;
+                report_dropped(&dropped);
+            }
+            _ = &mut shutdown_rx => {
+                // 进程正在优雅关闭，把channel里排队的记录和当前batch一起发完
+                while let Ok(record) = rx.try_recv() {
+                    batch.push(record);
+                }
+                flush(&client, &url, &username, &password, &mut batch).await;
+                report_dropped(&dropped);
+                break;
+            }
+        }
+    }
+}
+
+// 把因背压丢弃的日志数量打印出来，方便运维观察丢弃率；计数在打印后清零
+fn report_dropped(dropped: &Arc<AtomicU64>) {
+    let count = dropped.swap(0, Ordering::Relaxed);
+    if count > 0 {
+        tracing::warn!("dropped {} log records due to log-ship backpressure", count);
+    }
+}
+
+// 把一批记录编码成Elasticsearch `_bulk` API要求的线格式并POST出去，发送失败只记日志、不重试：
+// 每条文档前面都要有一行action/metadata（这里统一用`index`，不指定目标索引，交给接收端的
+// 默认索引/别名处理），文档行和action行都以换行结尾，整个body也必须以换行收尾
+async fn flush(
+    client: &reqwest::Client,
+    url: &str,
+    username: &Option<String>,
+    password: &Option<String>,
+    batch: &mut Vec<LogRecord>,
+) {
+    if batch.is_empty() {
+        return;
+    }
+
+    let mut body = String::new();
+    for record in batch.iter() {
+        let Ok(doc) = serde_json::to_string(record) else {
+            continue;
+        };
+        body.push_str("{\"index\":{}}\n");
+        body.push_str(&doc);
+        body.push('\n');
+    }
+
+    let mut request = client
+        .post(url)
+        .header("Content-Type", "application/x-ndjson")
+        .body(body);
+
+    if let Some(username) = username {
+        request = request.basic_auth(username, password.as_ref());
+    }
+
+    if let Err(err) = request.send().await {
+        tracing::warn!("failed to ship logs: {}", err);
+    }
+
+    batch.clear();
+}
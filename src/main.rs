@@ -1,9 +1,8 @@
 // 引入axum框架中的各种模块和函数
 use axum::{
-    extract::{Json, Path, Query, State}, // 用于提取请求中的数据
+    extract::{Path, Query, State}, // 用于提取请求中的数据
     http::StatusCode, // HTTP状态码
-    response::IntoResponse, // 响应转换
-    routing::{get, post}, // 路由处理
+    routing::{delete, get, post, put}, // 路由处理
     Router, // 路由器
 };
 
@@ -11,6 +10,9 @@ use axum::{
 use bb8::Pool;
 use bb8_postgres::PostgresConnectionManager;
 
+// 引入clap用于解析命令行参数
+use clap::Parser;
+
 // 引入serde库用于序列化和反序列化
 use serde::{Deserialize, Serialize};
 
@@ -23,49 +25,214 @@ use tower_http::trace::TraceLayer;
 // 引入uuid库生成唯一标识符
 use uuid::Uuid;
 
+mod error;
+use error::{AppError, Resp};
+
+mod lists;
+
+mod log_ship;
+use log_ship::{LogShipLayer, ShipperHandle};
+
+mod validation;
+use validation::{validate_uuid_path, ValidatedJson};
+
+use tracing_subscriber::prelude::*;
+use validator::Validate;
+
 // 定义数据库连接池类型
-type ConnectionPool = Pool<PostgresConnectionManager<NoTls>>;
+pub(crate) type ConnectionPool = Pool<PostgresConnectionManager<NoTls>>;
+
+// 命令行参数，用于配置数据库连接和监听地址
+#[derive(Debug, Parser)]
+#[command(author, version, about)]
+struct Args {
+    /// Postgres主机地址
+    #[arg(long, default_value = "localhost")]
+    host: String,
+
+    /// Postgres用户名
+    #[arg(long, default_value = "postgres")]
+    user: String,
+
+    /// Postgres密码
+    #[arg(long, default_value = "changeme")]
+    password: String,
+
+    /// Postgres数据库名
+    #[arg(long, default_value = "todolist")]
+    dbname: String,
+
+    /// Postgres端口
+    #[arg(long, default_value_t = 5432)]
+    port: u16,
+
+    /// HTTP服务监听地址
+    #[arg(long, default_value = "127.0.0.1:3000")]
+    listen_addr: String,
+
+    /// 远程日志收集器的批量写入地址，留空则只输出到stdout
+    #[arg(long)]
+    log_ship_url: Option<String>,
+
+    /// 访问远程日志收集器使用的basic auth用户名
+    #[arg(long)]
+    log_ship_user: Option<String>,
+
+    /// 访问远程日志收集器使用的basic auth密码
+    #[arg(long)]
+    log_ship_password: Option<String>,
+}
 
 #[tokio::main] // 声明异步主函数
 async fn main() {
-    // 初始化tracing_subscriber用于日志记录
-    tracing_subscriber::fmt::init();
+    // 解析命令行参数
+    let args = Args::parse();
+
+    // 初始化tracing_subscriber，按需叠加日志转发层；`shipper_handle`留到进程关闭时
+    // 触发最后一次flush
+    let registry = tracing_subscriber::registry().with(tracing_subscriber::fmt::layer());
+    let shipper_handle: Option<ShipperHandle> = match &args.log_ship_url {
+        Some(url) => {
+            let (ship_layer, handle) = LogShipLayer::new(
+                url.clone(),
+                args.log_ship_user.clone(),
+                args.log_ship_password.clone(),
+            );
+            registry.with(ship_layer).init();
+            Some(handle)
+        }
+        None => {
+            registry.init();
+            None
+        }
+    };
+
+    // 根据命令行参数拼接数据库连接字符串
+    let conn_string = format!(
+        "host={} port={} user={} password={} dbname={}",
+        args.host, args.port, args.user, args.password, args.dbname
+    );
 
     // 创建Postgres连接管理器
-    let manager = PostgresConnectionManager::new_from_stringlike(
-        "host=localhost user=postgres dbname=todolist password=changeme", // 数据库连接字符串
-        NoTls,
-    ).unwrap(); // 处理错误
+    let manager =
+        PostgresConnectionManager::new_from_stringlike(&conn_string, NoTls).unwrap_or_else(|err| {
+            tracing::error!("invalid postgres connection string: {}", err); // 连接字符串非法
+            std::process::exit(1);
+        });
 
     // 构建连接池
-    let pool = Pool::builder().build(manager).await.unwrap(); // 异步构建连接池并处理错误
+    let pool = Pool::builder().build(manager).await.unwrap_or_else(|err| {
+        tracing::error!("failed to build connection pool: {}", err); // 连接池构建失败，快速失败
+        std::process::exit(1);
+    });
+
+    // 启动时执行一次幂等的建表语句，保证服务可以对着一个全新的数据库直接启动
+    bootstrap_schema(&pool).await.unwrap_or_else(|err| {
+        tracing::error!("failed to bootstrap schema: {}", err);
+        std::process::exit(1);
+    });
 
     // 创建axum路由器
     let app = Router::new()
         .route("/todos", get(todos_list)) // 定义GET /todos路由
         .route("/todo/new", post(todo_create)) // 定义POST /todo/new路由
-        .route("/todo/update", post(todo_update)) // 定义POST /todo/update路由
-        .route("/todo/delete/:id", post(todo_delete)) // 定义POST /todo/delete/:id路由
+        .route("/todo/:id", put(todo_update)) // 定义PUT /todo/:id路由
+        .route("/todo/:id", delete(todo_delete)) // 定义DELETE /todo/:id路由
+        .route("/todo/:id/rendered", get(todo_rendered)) // 定义GET /todo/:id/rendered路由
+        .merge(lists::routes()) // 合并todo_list/todo_item子系统的路由
         .with_state(pool); // 传递数据库连接池状态
 
     // 绑定到指定地址并启动服务
-    let listener = tokio::net::TcpListener::bind("127.0.0.1:3000")
+    let listener = tokio::net::TcpListener::bind(&args.listen_addr)
         .await
         .unwrap(); // 处理错误
     tracing::debug!("listening on {}", listener.local_addr().unwrap()); // 记录监听地址
-    axum::serve(listener, app).await.unwrap(); // 启动服务并处理错误
+    axum::serve(listener, app)
+        .with_graceful_shutdown(shutdown_signal())
+        .await
+        .unwrap(); // 启动服务并处理错误
+
+    // 服务已经停止接受新连接，关闭日志转发的后台任务，把残留记录flush完再退出进程
+    if let Some(handle) = shipper_handle {
+        handle.shutdown().await;
+    }
+}
+
+// 等待Ctrl+C或者SIGTERM，用于触发axum的优雅关闭
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("failed to install Ctrl+C handler");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("failed to install SIGTERM handler")
+            .recv()
+            .await;
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+
+    tracing::info!("shutdown signal received");
+}
+
+// 幂等地创建todo表，使服务可以直接对着一个全新的数据库启动
+async fn bootstrap_schema(pool: &ConnectionPool) -> Result<(), AppError> {
+    let conn = pool.get().await?; // 从连接池获取连接
+
+    conn.execute(
+        "create table if not exists todo (
+            id text primary key,
+            description varchar(512) not null,
+            completed bool not null default false
+        )",
+        &[],
+    )
+    .await?;
+
+    conn.execute(
+        "create table if not exists todo_list (
+            id text primary key,
+            title varchar(512) not null
+        )",
+        &[],
+    )
+    .await?;
+
+    conn.execute(
+        "create table if not exists todo_item (
+            id text primary key,
+            list_id text not null references todo_list(id),
+            title varchar(512) not null,
+            checked bool not null default false
+        )",
+        &[],
+    )
+    .await?;
+
+    Ok(())
 }
 
 // 定义创建待办事项的数据结构
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Validate)]
 struct CreateTodo {
+    #[validate(length(min = 1, max = 512, message = "must be 1 to 512 characters"))]
     description: String,
 }
 
 // 定义更新待办事项的数据结构
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Validate)]
 struct UpdateTodo {
-    id: String,
+    #[validate(length(min = 1, max = 512, message = "must be 1 to 512 characters"))]
     description: Option<String>,
     completed: Option<bool>,
 }
@@ -83,54 +250,101 @@ struct Todo {
 pub struct Pagination {
     pub offset: Option<i64>,
     pub limit: Option<i64>,
+    // 当取值为"html"时，todos_list会把每条description内联渲染成HTML
+    pub format: Option<String>,
+}
+
+// 把Markdown描述渲染成HTML后返回的结构，只含id和html；数据库里的description仍保留原始Markdown
+#[derive(Debug, Serialize)]
+struct RenderedTodo {
+    id: String,
+    html: String,
+}
+
+// 把Markdown文本渲染成HTML字符串，再用ammonia清洗一遍，避免description里被塞进的
+// <script>之类的原始HTML被原样回显造成存储型XSS
+fn render_markdown(markdown: &str) -> String {
+    let parser = pulldown_cmark::Parser::new(markdown);
+    let mut html = String::new();
+    pulldown_cmark::html::push_html(&mut html, parser);
+    ammonia::clean(&html)
 }
 
 // 创建待办事项的处理函数
 async fn todo_create(
     State(pool): State<ConnectionPool>, // 提取数据库连接池状态
-    Json(input): Json<CreateTodo>, // 提取并解析请求体中的JSON数据
-) -> Result<(StatusCode, Json<Todo>), (StatusCode, String)> {
+    ValidatedJson(input): ValidatedJson<CreateTodo>, // 提取JSON数据并校验字段
+) -> Result<Resp<Todo>, AppError> {
     let todo = Todo {
         id: Uuid::new_v4().simple().to_string(), // 生成唯一标识符
         description: input.description, // 设置描述
         completed: false, // 设置为未完成
     };
 
-    let conn = pool.get().await.map_err(internal_error)?; // 从连接池获取连接并处理错误
+    let conn = pool.get().await?; // 从连接池获取连接
 
-    let _ret = conn
-        .execute(
-            "insert into todo (id, description, completed) values ($1, $2, $3) returning id",
-            &[&todo.id, &todo.description, &todo.completed], // 插入数据
-        )
-        .await
-        .map_err(internal_error)?; // 处理错误
+    conn.execute(
+        "insert into todo (id, description, completed) values ($1, $2, $3) returning id",
+        &[&todo.id, &todo.description, &todo.completed], // 插入数据
+    )
+    .await?;
 
-    Ok((StatusCode::CREATED, Json(todo))) // 返回创建的待办事项和状态码
+    Ok(Resp::new(StatusCode::CREATED, todo)) // 返回创建的待办事项和状态码
 }
 
 // 更新待办事项的处理函数
 async fn todo_update(
+    Path(id): Path<String>, // 提取路径参数中的ID
     State(pool): State<ConnectionPool>, // 提取数据库连接池状态
-    Json(utodo): Json<UpdateTodo>, // 提取并解析请求体中的JSON数据
-) -> Result<(StatusCode, Json<String>), (StatusCode, String)> {
-    Ok((StatusCode::OK, Json(utodo.id))) // 返回状态码和待办事项ID
+    ValidatedJson(utodo): ValidatedJson<UpdateTodo>, // 提取JSON数据并校验字段
+) -> Result<Resp<Todo>, AppError> {
+    validate_uuid_path("id", &id)?; // 校验路径中的ID，失败时返回和请求体校验一致的422字段错误
+
+    let conn = pool.get().await?; // 从连接池获取连接
+
+    let rows = conn
+        .query(
+            "update todo set description = coalesce($2, description), completed = coalesce($3, completed) where id = $1 returning id, description, completed",
+            &[&id, &utodo.description, &utodo.completed], // 动态更新字段
+        )
+        .await?;
+
+    let row = rows
+        .into_iter()
+        .next()
+        .ok_or_else(|| AppError::NotFound("record not found".to_string()))?; // 没有匹配的行则返回404
+
+    let todo = Todo {
+        id: row.get(0),
+        description: row.get(1),
+        completed: row.get(2),
+    }; // 由返回行重新构造待办事项
+
+    Ok(Resp::ok(todo)) // 返回更新后的待办事项
 }
 
 // 删除待办事项的处理函数
 async fn todo_delete(
     Path(id): Path<String>, // 提取路径参数中的ID
     State(pool): State<ConnectionPool>, // 提取数据库连接池状态
-) -> Result<(StatusCode, Json<String>), (StatusCode, String)> {
-    Ok((StatusCode::OK, Json(id))) // 返回状态码和待办事项ID
+) -> Result<Resp<()>, AppError> {
+    let conn = pool.get().await?; // 从连接池获取连接
+
+    let deleted = conn.execute("delete from todo where id = $1", &[&id]).await?; // 删除对应ID的记录
+
+    if deleted == 0 {
+        return Err(AppError::NotFound("record not found".to_string())); // 没有删除任何行则返回404
+    }
+
+    Ok(Resp::new(StatusCode::NO_CONTENT, ())) // 删除成功返回204
 }
 
 // 列出所有待办事项的处理函数
 async fn todos_list(
     pagination: Option<Query<Pagination>>, // 提取查询参数
     State(pool): State<ConnectionPool>, // 提取数据库连接池状态
-) -> Result<Json<Vec<Todo>>, (StatusCode, String)> {
-    let conn = pool.get().await.map_err(internal_error)?; // 从连接池获取连接并处理错误
+) -> Result<Resp<Vec<Todo>>, AppError> {
+    let conn = pool.get().await?; // 从连接池获取连接
     let Query(pagination) = pagination.unwrap_or_default(); // 获取分页参数
     let offset: i64 = pagination.offset.unwrap_or(0); // 设置偏移量
     let limit: i64 = pagination.limit.unwrap_or(100); // 设置限制
@@ -140,16 +354,20 @@ async fn todos_list(
             "select id, description, completed from todo offset $1 limit $2",
             &[&offset, &limit], // 查询数据
         )
-        .await
-        .map_err(internal_error)?; // 处理错误
+        .await?;
 
-    println!("rows:{:?}", rows); // 打印查询结果
+    let render_html = pagination.format.as_deref() == Some("html"); // 是否需要内联渲染HTML
 
     let mut todos: Vec<Todo> = Vec::new(); // 创建待办事项向量
     for row in rows {
         let id = row.get(0); // 获取ID
-        let description = row.get(1); // 获取描述
+        let description: String = row.get(1); // 获取描述
         let completed = row.get(2); // 获取完成状态
+        let description = if render_html {
+            render_markdown(&description) // 将Markdown内联渲染成HTML
+        } else {
+            description // 保持原始Markdown不变
+        };
         let todo = Todo {
             id,
             description,
@@ -158,13 +376,41 @@ async fn todos_list(
         todos.push(todo); // 添加到向量
     }
 
-    Ok(Json(todos)) // 返回待办事项向量
+    Ok(Resp::ok(todos)) // 返回待办事项向量
 }
 
-// 内部错误处理函数
-fn internal_error<E>(err: E) -> (StatusCode, String)
-where
-    E: std::error::Error,
-{
-    (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()) // 返回内部服务器错误和错误信息
+// 获取单条待办事项并将其描述渲染成HTML的处理函数
+async fn todo_rendered(
+    Path(id): Path<String>, // 提取路径参数中的ID
+    State(pool): State<ConnectionPool>, // 提取数据库连接池状态
+) -> Result<Resp<RenderedTodo>, AppError> {
+    let conn = pool.get().await?; // 从连接池获取连接
+
+    let row = conn
+        .query_opt(
+            "select id, description from todo where id = $1",
+            &[&id], // 按ID查询单条记录
+        )
+        .await?
+        .ok_or_else(|| AppError::NotFound("record not found".to_string()))?; // 没有匹配的行则返回404
+
+    let id: String = row.get(0);
+    let description: String = row.get(1);
+
+    Ok(Resp::ok(RenderedTodo {
+        id,
+        html: render_markdown(&description), // 数据库中保留原始Markdown，读取时渲染
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::render_markdown;
+
+    #[test]
+    fn render_markdown_strips_raw_script_tags() {
+        let html = render_markdown("hello <script>alert('xss')</script> world");
+        assert!(!html.contains("<script"));
+        assert!(!html.contains("alert("));
+    }
 }
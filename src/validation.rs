@@ -0,0 +1,49 @@
+// 在反序列化之后自动跑一遍`validator`校验的JSON提取器，以及给路径参数复用的UUID校验
+use axum::{
+    async_trait,
+    extract::{FromRequest, Json, Request},
+};
+use serde::de::DeserializeOwned;
+use uuid::Uuid;
+use validator::{Validate, ValidationError, ValidationErrors};
+
+use crate::error::AppError;
+
+// 包一层`Json<T>`，在提取成功后额外调用`Validate::validate`；反序列化失败和校验失败
+// 都转换成`AppError`，和其它处理函数共用同一套{code, msg, data}信封
+pub struct ValidatedJson<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequest<S> for ValidatedJson<T>
+where
+    T: DeserializeOwned + Validate,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let Json(value) = Json::<T>::from_request(req, state)
+            .await
+            .map_err(|err| AppError::BadRequest(err.to_string()))?;
+
+        value.validate()?;
+
+        Ok(ValidatedJson(value))
+    }
+}
+
+// 校验一个路径参数是否为合法UUID，失败时返回和`ValidatedJson`同样形状的422字段错误，
+// 而不是单独的400纯文本错误
+pub fn validate_uuid_path(field: &'static str, value: &str) -> Result<(), AppError> {
+    if Uuid::parse_str(value).is_ok() {
+        return Ok(());
+    }
+
+    let mut error = ValidationError::new("uuid");
+    error.message = Some("must be a valid uuid".into());
+
+    let mut errors = ValidationErrors::new();
+    errors.add(field, error);
+
+    Err(AppError::from(errors))
+}